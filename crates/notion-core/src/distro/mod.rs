@@ -1,6 +1,8 @@
 //! Provides types for fetching tool distributions into the local inventory.
 
 pub mod node;
+pub mod progress;
+pub mod version_spec;
 pub mod yarn;
 
 use crate::error::ErrorDetails;
@@ -11,7 +13,11 @@ use archive::HttpError;
 use notion_fail::Fallible;
 use reqwest::StatusCode;
 use semver::Version;
-use std::fs::File;
+use std::fs::{self, File};
+use std::path::Path;
+
+pub use self::progress::Progress;
+pub use self::version_spec::ToolVersionSpec;
 
 /// The result of a requested installation.
 #[derive(Debug)]
@@ -38,24 +44,90 @@ impl<V> Fetched<V> {
     }
 }
 
+/// The result of a requested uninstallation.
+#[derive(Debug)]
+pub enum Removed<V> {
+    /// Indicates that the given tool was installed and has now been removed.
+    Was(V),
+    /// Indicates that the given tool was not installed, so there was nothing to remove.
+    Missing(V),
+}
+
+impl<V> Removed<V> {
+    /// Consumes this value and produces the version that was (or would have been) removed.
+    pub fn into_version(self) -> V {
+        match self {
+            Removed::Was(version) | Removed::Missing(version) => version,
+        }
+    }
+}
+
 pub trait Distro: Sized {
     type VersionDetails;
 
-    /// Provision a distribution from the public distributor (e.g. `https://nodejs.org`).
-    fn public(version: Version) -> Fallible<Self>;
+    /// Provision a distribution from the public distributor (e.g. `https://nodejs.org`),
+    /// reporting download progress through `progress`.
+    fn public(version: Version, progress: &mut dyn Progress) -> Fallible<Self>;
 
-    /// Provision a distribution from a remote distributor.
-    fn remote(version: Version, url: &str) -> Fallible<Self>;
+    /// Provision a distribution from a remote distributor, reporting download progress
+    /// through `progress`.
+    fn remote(version: Version, url: &str, progress: &mut dyn Progress) -> Fallible<Self>;
 
     /// Provision a distribution from the filesystem.
     fn local(version: Version, file: File) -> Fallible<Self>;
 
+    /// Resolves a fuzzy `ToolVersionSpec` (e.g. `lts`, `^18`, a codename) to the concrete
+    /// `Version` it refers to, by consulting the public distributor's index.
+    fn resolve(spec: ToolVersionSpec) -> Fallible<Version>;
+
     /// Produces a reference to this distro's Tool version.
     fn version(&self) -> &Version;
 
     /// Fetches this version of the Tool. (It is left to the responsibility of the `Collection`
     /// to update its state after fetching succeeds.)
-    fn fetch(self, collection: &Collection<Self>) -> Fallible<Fetched<Self::VersionDetails>>;
+    ///
+    /// Ordinarily, if this version is already present in `collection`, the existing install is
+    /// left untouched and `Fetched::Already` is returned without unpacking the archive. (The
+    /// archive was already obtained when this distro was provisioned; `Collection::fetch` is
+    /// what skips provisioning, and so the download, for a version already installed.) If
+    /// `force` is set, the version is re-downloaded and re-extracted regardless, replacing any
+    /// existing install only once the new one has been fully staged; the result still reports
+    /// `Fetched::Now`. This repairs installs left corrupt or partial by an earlier interrupted
+    /// fetch, without requiring the caller to manually delete files first.
+    fn fetch(
+        self,
+        collection: &Collection<Self>,
+        force: bool,
+    ) -> Fallible<Fetched<Self::VersionDetails>>;
+}
+
+/// Promotes a freshly-unpacked `staging_dir` into `inventory_dir` as `version`, replacing any
+/// existing install there. Shared by every `Distro::fetch` implementation's force-repair and
+/// first-fetch paths, so the replace sequence only has to be gotten right once.
+///
+/// The existing install (if any) is renamed aside first and only deleted once the new one is
+/// in place, so the old files are never deleted before the new ones are installed. Between the
+/// two renames the version's directory does not exist; if the process stops there (or the
+/// second rename fails), the old install is left in the backup directory and is not restored.
+pub(crate) fn promote_install(
+    inventory_dir: &Path,
+    tool_tag: &str,
+    version: &Version,
+    staging_dir: &Path,
+) -> Fallible<()> {
+    let install_dir = inventory_dir.join(version.to_string());
+    let backup_dir = inventory_dir.join(format!(".backup-{}-{}", tool_tag, version));
+
+    let had_existing_install = install_dir.is_dir();
+    if had_existing_install {
+        fs::rename(&install_dir, &backup_dir)?;
+    }
+    fs::rename(staging_dir, &install_dir)?;
+    if had_existing_install {
+        fs::remove_dir_all(&backup_dir)?;
+    }
+
+    Ok(())
 }
 
 fn download_tool_error(
@@ -0,0 +1,195 @@
+//! Provides the `NodeDistro` type, which represents a provisioned Node installation.
+
+use std::env;
+use std::fs::{self, File};
+
+use super::progress::{download_to_file, Progress};
+use super::{download_tool_error, Distro, Fetched, ToolVersionSpec};
+use crate::error::ErrorDetails;
+use crate::inventory::Collection;
+use crate::tool::ToolSpec;
+use archive::{Archive, Origin};
+use notion_fail::Fallible;
+use semver::Version;
+use serde::Deserialize;
+
+const PUBLIC_NODE_SERVER_ROOT: &str = "https://nodejs.org/dist";
+const PUBLIC_NODE_INDEX_URL: &str = "https://nodejs.org/dist/index.json";
+
+/// A provisioned Node distribution.
+pub struct NodeDistro {
+    archive: Box<dyn Archive>,
+    version: Version,
+    /// The temp file the archive was streamed into, if it came from a remote download;
+    /// cleaned up once the archive has been unpacked into the inventory.
+    download_path: Option<std::path::PathBuf>,
+}
+
+/// The path a Node tarball is streamed into while it downloads, so a download can resume
+/// across invocations if it's interrupted partway through.
+fn download_dest(version: &Version) -> std::path::PathBuf {
+    env::temp_dir().join(format!("notion-node-{}.download", version))
+}
+
+/// One row of the public `index.json` served alongside the Node distributions.
+#[derive(Deserialize)]
+struct NodeIndexEntry {
+    version: Version,
+    lts: NodeLtsField,
+}
+
+/// The `lts` field of a Node index entry: either `false`, or the LTS codename.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NodeLtsField {
+    NotLts(bool),
+    Codename(String),
+}
+
+impl NodeLtsField {
+    fn codename(&self) -> Option<&str> {
+        match self {
+            NodeLtsField::Codename(name) => Some(name.as_str()),
+            NodeLtsField::NotLts(_) => None,
+        }
+    }
+}
+
+/// Downloads and parses the public Node distribution index.
+fn fetch_index() -> Fallible<Vec<NodeIndexEntry>> {
+    reqwest::get(PUBLIC_NODE_INDEX_URL)
+        .and_then(|mut response| response.json())
+        .map_err(|error| {
+            ErrorDetails::ToolIndexFetchError {
+                tool: "node".into(),
+                from_url: PUBLIC_NODE_INDEX_URL.into(),
+                error: error.to_string(),
+            }
+            .into()
+        })
+}
+
+/// Picks the newest version among the index entries matching `matches`.
+fn max_matching(entries: &[NodeIndexEntry], matches: impl Fn(&NodeIndexEntry) -> bool) -> Option<Version> {
+    entries
+        .iter()
+        .filter(|entry| matches(entry))
+        .map(|entry| entry.version.clone())
+        .max()
+}
+
+impl Distro for NodeDistro {
+    type VersionDetails = Version;
+
+    fn public(version: Version, progress: &mut dyn Progress) -> Fallible<Self> {
+        let url = format!(
+            "{}/v{}/node-v{}-{}.{}",
+            PUBLIC_NODE_SERVER_ROOT,
+            version,
+            version,
+            node_archive_os_tag(),
+            node_archive_extension()
+        );
+        NodeDistro::remote(version, &url, progress)
+    }
+
+    fn remote(version: Version, url: &str, progress: &mut dyn Progress) -> Fallible<Self> {
+        let dest = download_dest(&version);
+        let file = download_to_file(
+            url,
+            &dest,
+            progress,
+            download_tool_error(ToolSpec::Node(version.clone()), url),
+        )?;
+        let archive = Archive::load(Origin::Remote, file).with_context(download_tool_error(
+            ToolSpec::Node(version.clone()),
+            url,
+        ))?;
+        Ok(NodeDistro {
+            archive,
+            version,
+            download_path: Some(dest),
+        })
+    }
+
+    fn local(version: Version, file: File) -> Fallible<Self> {
+        let archive = Archive::load(Origin::Local, file).with_context(download_tool_error(
+            ToolSpec::Node(version.clone()),
+            "<local file>",
+        ))?;
+        Ok(NodeDistro {
+            archive,
+            version,
+            download_path: None,
+        })
+    }
+
+    fn resolve(spec: ToolVersionSpec) -> Fallible<Version> {
+        let index = fetch_index()?;
+        let resolved = match &spec {
+            ToolVersionSpec::Latest => max_matching(&index, |_| true),
+            ToolVersionSpec::Lts => max_matching(&index, |entry| entry.lts.codename().is_some()),
+            ToolVersionSpec::LtsNamed(name) => max_matching(&index, |entry| {
+                entry
+                    .lts
+                    .codename()
+                    .map_or(false, |codename| codename.eq_ignore_ascii_case(name))
+            }),
+            ToolVersionSpec::Range(req) => max_matching(&index, |entry| req.matches(&entry.version)),
+        };
+
+        resolved.ok_or_else(|| {
+            ErrorDetails::NoToolVersionFound {
+                tool: "node".into(),
+                spec: format!("{:?}", spec),
+            }
+            .into()
+        })
+    }
+
+    fn version(&self) -> &Version {
+        &self.version
+    }
+
+    fn fetch(
+        self,
+        collection: &Collection<Self>,
+        force: bool,
+    ) -> Fallible<Fetched<Self::VersionDetails>> {
+        if !force && collection.contains(&self.version) {
+            return Ok(Fetched::Already(self.version));
+        }
+
+        let staging_dir = collection
+            .inventory_dir()
+            .join(format!(".staging-node-{}", self.version));
+
+        self.archive.unpack(&staging_dir)?;
+        super::promote_install(collection.inventory_dir(), "node", &self.version, &staging_dir)?;
+
+        if let Some(download_path) = &self.download_path {
+            let _ = fs::remove_file(download_path);
+        }
+        Ok(Fetched::Now(self.version))
+    }
+}
+
+/// The OS/arch tag nodejs.org uses in its tarball names, e.g. `linux-x64`.
+fn node_archive_os_tag() -> &'static str {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("macos", "aarch64") => "darwin-arm64",
+        ("macos", _) => "darwin-x64",
+        ("linux", "aarch64") => "linux-arm64",
+        ("linux", _) => "linux-x64",
+        ("windows", _) => "win-x64",
+        _ => "linux-x64",
+    }
+}
+
+fn node_archive_extension() -> &'static str {
+    if cfg!(windows) {
+        "zip"
+    } else {
+        "tar.gz"
+    }
+}
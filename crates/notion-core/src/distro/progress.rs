@@ -0,0 +1,234 @@
+//! Provides a pluggable `Progress` trait and the streaming, resumable download routine shared
+//! by the `node` and `yarn` distros' remote fetch paths.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::error::ErrorDetails;
+use notion_fail::Fallible;
+use reqwest::header::{CONTENT_LENGTH, RANGE};
+use reqwest::StatusCode;
+
+/// The size of each chunk read from the response body and written to disk.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Receives progress updates as a download streams in, so that a CLI can render a progress
+/// bar for large tarballs. `total` is `None` when the server didn't report a content length.
+pub trait Progress {
+    fn update(&mut self, downloaded: u64, total: Option<u64>);
+}
+
+/// A `Progress` implementation that reports nothing; used where no bar is being rendered.
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn update(&mut self, _downloaded: u64, _total: Option<u64>) {}
+}
+
+/// Streams `url` into `dest` chunk-by-chunk, reporting progress through `progress`.
+///
+/// If `dest` already holds a partial download (e.g. left over from an interrupted fetch), the
+/// request resumes from its current length via a `Range` header instead of starting over. If
+/// the server doesn't honor the range (answers `200 OK` instead of `206 Partial Content`),
+/// the partial file is discarded and the download restarts from scratch. If `dest` already
+/// holds a *complete* download (e.g. the process crashed after the download finished but
+/// before the archive was unpacked), the server will reject the resulting unsatisfiable range
+/// with `416 Range Not Satisfiable`; rather than surfacing that as a hard error, the existing
+/// file is reused as-is. Once the body has been fully received, the bytes actually written are
+/// checked against the server-reported length before the file is handed back to the caller.
+pub fn download_to_file(
+    url: &str,
+    dest: &Path,
+    progress: &mut dyn Progress,
+    on_error: impl FnOnce(&failure::Error) -> ErrorDetails,
+) -> Fallible<File> {
+    let already_downloaded = dest.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+
+    let client = reqwest::Client::new();
+    let mut request = client.get(url);
+    if already_downloaded > 0 {
+        request = request.header(RANGE, format!("bytes={}-", already_downloaded));
+    }
+
+    let response = request.send().map_err(|error| on_error(&failure::Error::from(error)))?;
+
+    if already_downloaded > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // The file on disk is already as large as (or larger than) what the server has to
+        // offer, i.e. the earlier download already finished. Trust it rather than erroring.
+        return Ok(File::open(dest)?);
+    }
+
+    let mut response = response
+        .error_for_status()
+        .map_err(|error| on_error(&failure::Error::from(error)))?;
+
+    let resuming = already_downloaded > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+
+    let mut file = if resuming {
+        OpenOptions::new().append(true).open(dest)?
+    } else {
+        File::create(dest)?
+    };
+
+    let mut downloaded = if resuming { already_downloaded } else { 0 };
+
+    let total = response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(|remaining| downloaded + remaining);
+
+    progress.update(downloaded, total);
+
+    let mut buffer = [0u8; CHUNK_SIZE];
+    loop {
+        let bytes_read = response.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..bytes_read])?;
+        downloaded += bytes_read as u64;
+        progress.update(downloaded, total);
+    }
+
+    if let Some(expected) = total {
+        if downloaded != expected {
+            return Err(ErrorDetails::IncompleteDownload {
+                from_url: url.to_string(),
+                expected,
+                actual: downloaded,
+            }
+            .into());
+        }
+    }
+
+    file.flush()?;
+    Ok(File::open(dest)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::net::TcpListener;
+    use std::thread;
+    use tempfile::tempdir;
+
+    /// Starts a background server that accepts a single connection, reads (and discards) the
+    /// request, then writes `response` verbatim before closing the connection. Returns the
+    /// `http://` URL it's listening on.
+    fn spawn_fake_server(response: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let mut buffer = [0u8; 4096];
+                let mut request = Vec::new();
+                loop {
+                    let bytes_read = stream.read(&mut buffer).unwrap_or(0);
+                    if bytes_read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..bytes_read]);
+                    if request.windows(4).any(|window| window == b"\r\n\r\n") {
+                        break;
+                    }
+                }
+                let _ = stream.write_all(&response);
+                let _ = stream.flush();
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+
+    fn http_response(status_line: &str, body: &[u8]) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status_line,
+            body.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    #[test]
+    fn downloads_a_fresh_file_in_full() {
+        let body = b"Hello, world!";
+        let url = spawn_fake_server(http_response("200 OK", body));
+
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("download");
+        let file =
+            download_to_file(&url, &dest, &mut NoProgress, |error| panic!("unexpected error: {}", error))
+                .unwrap();
+        drop(file);
+
+        assert_eq!(fs::read(&dest).unwrap(), body);
+    }
+
+    #[test]
+    fn resumes_a_partial_download_with_a_206_response() {
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("download");
+        fs::write(&dest, b"Hello").unwrap();
+
+        let remaining = b", world!";
+        let url = spawn_fake_server(http_response("206 Partial Content", remaining));
+
+        download_to_file(&url, &dest, &mut NoProgress, |error| panic!("unexpected error: {}", error))
+            .unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), b"Hello, world!");
+    }
+
+    #[test]
+    fn restarts_from_scratch_when_the_server_ignores_the_range_header() {
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("download");
+        fs::write(&dest, b"XXXXX").unwrap();
+
+        let full_body = b"Hello, world!";
+        let url = spawn_fake_server(http_response("200 OK", full_body));
+
+        download_to_file(&url, &dest, &mut NoProgress, |error| panic!("unexpected error: {}", error))
+            .unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), full_body);
+    }
+
+    #[test]
+    fn reuses_an_already_complete_download_on_a_416_response() {
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("download");
+        fs::write(&dest, b"already complete").unwrap();
+
+        let url = spawn_fake_server(http_response("416 Range Not Satisfiable", b""));
+
+        download_to_file(&url, &dest, &mut NoProgress, |error| panic!("unexpected error: {}", error))
+            .unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), b"already complete");
+    }
+
+    #[test]
+    fn errors_when_fewer_bytes_arrive_than_the_server_promised() {
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("download");
+
+        // Claim 20 bytes are coming but only send 10 before closing the connection.
+        let mut response =
+            b"HTTP/1.1 200 OK\r\nContent-Length: 20\r\nConnection: close\r\n\r\n".to_vec();
+        response.extend_from_slice(b"0123456789");
+        let url = spawn_fake_server(response);
+
+        let result =
+            download_to_file(&url, &dest, &mut NoProgress, |error| panic!("unexpected error: {}", error));
+
+        assert!(result.is_err());
+    }
+}
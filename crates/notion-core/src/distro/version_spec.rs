@@ -0,0 +1,86 @@
+//! Provides `ToolVersionSpec`, a user-facing request for a tool version that has not yet
+//! been resolved to a concrete, installable `semver::Version`.
+
+use semver::VersionReq;
+
+/// A fuzzy request for a tool version, as typed by the user (e.g. `lts`, `v18`, `^12.2.0`,
+/// or an LTS codename like `Hydrogen`). `Distro::resolve` turns this into a concrete
+/// `semver::Version` by consulting the public distributor's index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolVersionSpec {
+    /// The newest version available from the public distributor.
+    Latest,
+    /// The newest version tagged as an LTS ("Long Term Support") release.
+    Lts,
+    /// The newest version released under a named LTS codename, e.g. `"Hydrogen"`.
+    LtsNamed(String),
+    /// The newest version satisfying a semver range, e.g. `^18` or `12.2.0`.
+    Range(VersionReq),
+}
+
+impl ToolVersionSpec {
+    /// Parses a user-supplied version string into a `ToolVersionSpec`.
+    ///
+    /// A leading `v` is trimmed (`v18` behaves like `18`). The remainder is first tried as a
+    /// `VersionReq`; if that fails to parse, it is treated as an LTS codename (so `Hydrogen`
+    /// and `lts` both resolve to LTS releases, while `lts` is special-cased to mean "any LTS").
+    pub fn parse(spec: impl AsRef<str>) -> ToolVersionSpec {
+        let trimmed = spec.as_ref().trim();
+        let unprefixed = trimmed.strip_prefix('v').unwrap_or(trimmed).trim();
+
+        if unprefixed.eq_ignore_ascii_case("latest") {
+            return ToolVersionSpec::Latest;
+        }
+
+        if unprefixed.eq_ignore_ascii_case("lts") {
+            return ToolVersionSpec::Lts;
+        }
+
+        match VersionReq::parse(unprefixed) {
+            Ok(req) => ToolVersionSpec::Range(req),
+            Err(_) => ToolVersionSpec::LtsNamed(unprefixed.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ToolVersionSpec;
+    use semver::VersionReq;
+
+    #[test]
+    fn parses_latest() {
+        assert_eq!(ToolVersionSpec::parse("latest"), ToolVersionSpec::Latest);
+        assert_eq!(ToolVersionSpec::parse("Latest"), ToolVersionSpec::Latest);
+    }
+
+    #[test]
+    fn parses_lts() {
+        assert_eq!(ToolVersionSpec::parse("lts"), ToolVersionSpec::Lts);
+        assert_eq!(ToolVersionSpec::parse("LTS"), ToolVersionSpec::Lts);
+    }
+
+    #[test]
+    fn parses_lts_codename_with_or_without_leading_v() {
+        assert_eq!(
+            ToolVersionSpec::parse("Hydrogen"),
+            ToolVersionSpec::LtsNamed("Hydrogen".into())
+        );
+        assert_eq!(
+            ToolVersionSpec::parse("vHydrogen"),
+            ToolVersionSpec::LtsNamed("Hydrogen".into())
+        );
+    }
+
+    #[test]
+    fn parses_ranges_with_leading_v() {
+        assert_eq!(
+            ToolVersionSpec::parse("v18"),
+            ToolVersionSpec::Range(VersionReq::parse("18").unwrap())
+        );
+        assert_eq!(
+            ToolVersionSpec::parse("^12.2.0"),
+            ToolVersionSpec::Range(VersionReq::parse("^12.2.0").unwrap())
+        );
+    }
+}
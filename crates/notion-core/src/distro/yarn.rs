@@ -0,0 +1,199 @@
+//! Provides the `YarnDistro` type, which represents a provisioned Yarn installation.
+
+use std::env;
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+use super::progress::{download_to_file, Progress};
+use super::{download_tool_error, Distro, Fetched, ToolVersionSpec};
+use crate::error::ErrorDetails;
+use crate::inventory::Collection;
+use crate::tool::ToolSpec;
+use archive::{Archive, Origin};
+use notion_fail::Fallible;
+use reqwest::header::LINK;
+use semver::Version;
+use serde::Deserialize;
+
+const PUBLIC_YARN_SERVER_ROOT: &str = "https://github.com/yarnpkg/yarn/releases/download";
+const PUBLIC_YARN_RELEASES_URL: &str = "https://api.github.com/repos/yarnpkg/yarn/releases";
+
+/// A provisioned Yarn distribution.
+pub struct YarnDistro {
+    archive: Box<dyn Archive>,
+    version: Version,
+    /// The temp file the archive was streamed into, if it came from a remote download;
+    /// cleaned up once the archive has been unpacked into the inventory.
+    download_path: Option<PathBuf>,
+}
+
+/// The path a Yarn tarball is streamed into while it downloads, so a download can resume
+/// across invocations if it's interrupted partway through.
+fn download_dest(version: &Version) -> PathBuf {
+    env::temp_dir().join(format!("notion-yarn-{}.download", version))
+}
+
+/// One entry of the public GitHub releases index for Yarn.
+#[derive(Deserialize)]
+struct YarnReleaseEntry {
+    tag_name: String,
+}
+
+/// Downloads and parses the public Yarn release index, following the `Link: rel="next"`
+/// header across every page rather than trusting GitHub's default single page of 30, so that
+/// older releases (needed to resolve e.g. a `Range` spec pinning an old version) aren't missed.
+fn fetch_versions() -> Fallible<Vec<Version>> {
+    let mut releases = Vec::new();
+    let mut next_url = Some(PUBLIC_YARN_RELEASES_URL.to_string());
+
+    while let Some(url) = next_url {
+        let mut response = reqwest::get(&url).map_err(|error| {
+            ErrorDetails::ToolIndexFetchError {
+                tool: "yarn".into(),
+                from_url: url.clone(),
+                error: error.to_string(),
+            }
+            .into()
+        })?;
+
+        next_url = response
+            .headers()
+            .get(LINK)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_next_link);
+
+        let page: Vec<YarnReleaseEntry> = response.json().map_err(|error| {
+            ErrorDetails::ToolIndexFetchError {
+                tool: "yarn".into(),
+                from_url: url.clone(),
+                error: error.to_string(),
+            }
+            .into()
+        })?;
+        releases.extend(page);
+    }
+
+    Ok(releases
+        .into_iter()
+        .filter_map(|release| Version::parse(release.tag_name.trim_start_matches('v')).ok())
+        .collect())
+}
+
+/// Extracts the `rel="next"` URL from a GitHub-style paginated `Link` header, e.g.
+/// `<https://api.github.com/...?page=2>; rel="next", <...>; rel="last"`.
+fn parse_next_link(header_value: &str) -> Option<String> {
+    header_value.split(',').find_map(|entry| {
+        let mut parts = entry.split(';').map(str::trim);
+        let url_part = parts.next()?;
+        let is_next = parts.any(|param| param == r#"rel="next""#);
+        if is_next {
+            Some(
+                url_part
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string(),
+            )
+        } else {
+            None
+        }
+    })
+}
+
+impl Distro for YarnDistro {
+    type VersionDetails = Version;
+
+    fn public(version: Version, progress: &mut dyn Progress) -> Fallible<Self> {
+        let url = format!(
+            "{}/v{}/yarn-v{}.tar.gz",
+            PUBLIC_YARN_SERVER_ROOT, version, version
+        );
+        YarnDistro::remote(version, &url, progress)
+    }
+
+    fn remote(version: Version, url: &str, progress: &mut dyn Progress) -> Fallible<Self> {
+        let dest = download_dest(&version);
+        let file = download_to_file(
+            url,
+            &dest,
+            progress,
+            download_tool_error(ToolSpec::Yarn(version.clone()), url),
+        )?;
+        let archive = Archive::load(Origin::Remote, file)
+            .with_context(download_tool_error(ToolSpec::Yarn(version.clone()), url))?;
+        Ok(YarnDistro {
+            archive,
+            version,
+            download_path: Some(dest),
+        })
+    }
+
+    fn local(version: Version, file: File) -> Fallible<Self> {
+        let archive = Archive::load(Origin::Local, file).with_context(download_tool_error(
+            ToolSpec::Yarn(version.clone()),
+            "<local file>",
+        ))?;
+        Ok(YarnDistro {
+            archive,
+            version,
+            download_path: None,
+        })
+    }
+
+    fn resolve(spec: ToolVersionSpec) -> Fallible<Version> {
+        match &spec {
+            ToolVersionSpec::Latest => {
+                fetch_versions()?.into_iter().max().ok_or_else(|| {
+                    ErrorDetails::NoToolVersionFound {
+                        tool: "yarn".into(),
+                        spec: format!("{:?}", spec),
+                    }
+                    .into()
+                })
+            }
+            ToolVersionSpec::Range(req) => fetch_versions()?
+                .into_iter()
+                .filter(|version| req.matches(version))
+                .max()
+                .ok_or_else(|| {
+                    ErrorDetails::NoToolVersionFound {
+                        tool: "yarn".into(),
+                        spec: format!("{:?}", spec),
+                    }
+                    .into()
+                }),
+            ToolVersionSpec::Lts | ToolVersionSpec::LtsNamed(_) => {
+                Err(ErrorDetails::NoToolVersionFound {
+                    tool: "yarn".into(),
+                    spec: "Yarn has no LTS releases".into(),
+                }
+                .into())
+            }
+        }
+    }
+
+    fn version(&self) -> &Version {
+        &self.version
+    }
+
+    fn fetch(
+        self,
+        collection: &Collection<Self>,
+        force: bool,
+    ) -> Fallible<Fetched<Self::VersionDetails>> {
+        if !force && collection.contains(&self.version) {
+            return Ok(Fetched::Already(self.version));
+        }
+
+        let staging_dir = collection
+            .inventory_dir()
+            .join(format!(".staging-yarn-{}", self.version));
+
+        self.archive.unpack(&staging_dir)?;
+        super::promote_install(collection.inventory_dir(), "yarn", &self.version, &staging_dir)?;
+
+        if let Some(download_path) = &self.download_path {
+            let _ = fs::remove_file(download_path);
+        }
+        Ok(Fetched::Now(self.version))
+    }
+}
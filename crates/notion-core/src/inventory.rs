@@ -0,0 +1,418 @@
+//! Provides the `Collection` type, representing the set of versions of a single tool (e.g.
+//! all locally fetched Node versions) that have already been installed into the inventory.
+
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use crate::distro::{Distro, Fetched, Progress, Removed};
+use crate::error::ErrorDetails;
+use notion_fail::Fallible;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+/// The file, stored inside a tool's inventory directory, that caches which versions are
+/// already installed so that `Collection::contains` doesn't have to rescan the directory.
+const CACHE_FILE_NAME: &str = "versions.json";
+
+/// A persisted, lazily-loaded lookup table of installed versions.
+#[derive(Default, Serialize, Deserialize)]
+struct InventoryCache {
+    versions: BTreeSet<Version>,
+}
+
+impl InventoryCache {
+    /// Loads the cache from `cache_file`. If the file is missing or fails to parse, the cache
+    /// is rebuilt from a full scan of `inventory_dir` and the rebuilt cache is persisted.
+    fn load(cache_file: &Path, inventory_dir: &Path) -> Fallible<InventoryCache> {
+        let loaded = match File::open(cache_file) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file)).ok(),
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error.into()),
+        };
+
+        match loaded {
+            Some(cache) => Ok(cache),
+            None => {
+                let cache = InventoryCache::rescan(inventory_dir)?;
+                cache.save(cache_file)?;
+                Ok(cache)
+            }
+        }
+    }
+
+    /// Rebuilds the cache from scratch by scanning the on-disk inventory directory.
+    fn rescan(inventory_dir: &Path) -> Fallible<InventoryCache> {
+        let mut versions = BTreeSet::new();
+
+        if inventory_dir.is_dir() {
+            for entry in fs::read_dir(inventory_dir)? {
+                let entry = entry?;
+                if let Some(name) = entry.file_name().to_str() {
+                    if let Ok(version) = Version::parse(name) {
+                        versions.insert(version);
+                    }
+                }
+            }
+        }
+
+        Ok(InventoryCache { versions })
+    }
+
+    /// Persists the cache to `cache_file`, creating its parent directory if needed.
+    fn save(&self, cache_file: &Path) -> Fallible<()> {
+        if let Some(parent) = cache_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = File::create(cache_file)?;
+        serde_json::to_writer(BufWriter::new(file), self)?;
+        Ok(())
+    }
+}
+
+/// The collection of locally installed versions of a single tool.
+pub struct Collection<D: Distro> {
+    inventory_dir: PathBuf,
+    cache_file: PathBuf,
+    cache: InventoryCache,
+    phantom: PhantomData<D>,
+}
+
+impl<D: Distro> Collection<D> {
+    /// Loads the collection backed by `inventory_dir`, consulting (and lazily rebuilding) its
+    /// persisted cache rather than rescanning the directory on every load.
+    pub fn load(inventory_dir: PathBuf) -> Fallible<Collection<D>> {
+        let cache_file = inventory_dir.join(CACHE_FILE_NAME);
+        let cache = InventoryCache::load(&cache_file, &inventory_dir)?;
+        Ok(Collection {
+            inventory_dir,
+            cache_file,
+            cache,
+            phantom: PhantomData,
+        })
+    }
+
+    /// True if `version` is already present in the inventory. Backed entirely by the
+    /// in-memory cache, so this never touches the filesystem.
+    pub fn contains(&self, version: &Version) -> bool {
+        self.cache.versions.contains(version)
+    }
+
+    /// Fetches `version`, short-circuiting to `Fetched::Already` *before* ever touching the
+    /// distributor when it's already cached. Only when the version is missing (or `force` is
+    /// set) does this call `construct` to provision the distro — which is what actually starts
+    /// the network download — and fetch it. On a successful fresh fetch, records the new
+    /// version in the cache and persists it immediately.
+    pub fn fetch(
+        &mut self,
+        version: &Version,
+        force: bool,
+        progress: &mut dyn Progress,
+        construct: impl FnOnce(&mut dyn Progress) -> Fallible<D>,
+    ) -> Fallible<Fetched<D::VersionDetails>>
+    where
+        D: Distro<VersionDetails = Version>,
+    {
+        if !force && self.contains(version) {
+            return Ok(Fetched::Already(version.clone()));
+        }
+
+        let distro = construct(progress)?;
+        let fetched = distro.fetch(self, force)?;
+
+        if let Fetched::Now(_) = fetched {
+            self.add(version)?;
+        }
+
+        Ok(fetched)
+    }
+
+    /// Records that `version` has just been installed, persisting the updated cache.
+    fn add(&mut self, version: &Version) -> Fallible<()> {
+        self.cache.versions.insert(version.clone());
+        self.cache.save(&self.cache_file)
+    }
+
+    /// Removes `version` from the inventory: deletes its install directory and updates the
+    /// persisted cache. Refuses to remove `version` if it is passed as `default_version`,
+    /// i.e. currently pinned as the active/default version for this tool.
+    pub fn remove(
+        &mut self,
+        version: &Version,
+        default_version: Option<&Version>,
+    ) -> Fallible<Removed<Version>> {
+        if !self.contains(version) {
+            return Ok(Removed::Missing(version.clone()));
+        }
+
+        if default_version == Some(version) {
+            return Err(ErrorDetails::UninstallPinnedVersion {
+                version: version.clone(),
+            }
+            .into());
+        }
+
+        let install_dir = self.inventory_dir.join(version.to_string());
+        if install_dir.is_dir() {
+            fs::remove_dir_all(&install_dir)?;
+        }
+
+        self.cache.versions.remove(version);
+        self.cache.save(&self.cache_file)?;
+
+        Ok(Removed::Was(version.clone()))
+    }
+
+    /// The directory backing this collection's inventory.
+    pub fn inventory_dir(&self) -> &Path {
+        &self.inventory_dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distro::progress::NoProgress;
+    use crate::distro::{Distro, Fetched, Progress, ToolVersionSpec};
+    use std::cell::Cell;
+    use std::fs::File;
+    use std::rc::Rc;
+    use tempfile::tempdir;
+
+    /// A fake `Distro` standing in for `NodeDistro`/`YarnDistro` in tests. `public`/`remote`/
+    /// `local`/`resolve` aren't exercised here and stay `unimplemented!()`; `fetch` mirrors the
+    /// real distros' stage-then-`promote_install` sequence, writing `marker` into the install
+    /// directory so tests can tell which fetch actually won.
+    struct TestDistro {
+        version: Version,
+        marker: String,
+    }
+
+    impl Distro for TestDistro {
+        type VersionDetails = Version;
+
+        fn public(_version: Version, _progress: &mut dyn Progress) -> Fallible<Self> {
+            unimplemented!()
+        }
+
+        fn remote(_version: Version, _url: &str, _progress: &mut dyn Progress) -> Fallible<Self> {
+            unimplemented!()
+        }
+
+        fn local(_version: Version, _file: File) -> Fallible<Self> {
+            unimplemented!()
+        }
+
+        fn resolve(_spec: ToolVersionSpec) -> Fallible<Version> {
+            unimplemented!()
+        }
+
+        fn version(&self) -> &Version {
+            &self.version
+        }
+
+        fn fetch(
+            self,
+            collection: &Collection<Self>,
+            force: bool,
+        ) -> Fallible<Fetched<Self::VersionDetails>> {
+            if !force && collection.contains(&self.version) {
+                return Ok(Fetched::Already(self.version));
+            }
+
+            let staging_dir = collection
+                .inventory_dir()
+                .join(format!(".staging-test-{}", self.version));
+            fs::create_dir_all(&staging_dir)?;
+            fs::write(staging_dir.join("marker"), self.marker.as_bytes())?;
+
+            crate::distro::promote_install(
+                collection.inventory_dir(),
+                "test",
+                &self.version,
+                &staging_dir,
+            )?;
+
+            Ok(Fetched::Now(self.version))
+        }
+    }
+
+    fn version(raw: &str) -> Version {
+        Version::parse(raw).unwrap()
+    }
+
+    #[test]
+    fn rebuilds_corrupt_cache_from_a_directory_rescan() {
+        let dir = tempdir().unwrap();
+        let inventory_dir = dir.path().to_path_buf();
+        fs::create_dir_all(inventory_dir.join("4.5.6")).unwrap();
+        fs::write(inventory_dir.join(CACHE_FILE_NAME), b"not valid json").unwrap();
+
+        let collection: Collection<TestDistro> = Collection::load(inventory_dir.clone()).unwrap();
+
+        assert!(collection.contains(&version("4.5.6")));
+
+        // The corrupt cache should have been rebuilt and persisted, not just patched in memory.
+        let rebuilt = fs::read_to_string(inventory_dir.join(CACHE_FILE_NAME)).unwrap();
+        assert!(rebuilt.contains("4.5.6"));
+    }
+
+    #[test]
+    fn loads_an_empty_cache_for_a_fresh_inventory_dir() {
+        let dir = tempdir().unwrap();
+        let collection: Collection<TestDistro> =
+            Collection::load(dir.path().to_path_buf()).unwrap();
+
+        assert!(!collection.contains(&version("1.0.0")));
+    }
+
+    #[test]
+    fn remove_reports_missing_when_the_version_is_not_installed() {
+        let dir = tempdir().unwrap();
+        let mut collection: Collection<TestDistro> =
+            Collection::load(dir.path().to_path_buf()).unwrap();
+
+        match collection.remove(&version("1.2.3"), None).unwrap() {
+            Removed::Missing(removed) => assert_eq!(removed, version("1.2.3")),
+            Removed::Was(_) => panic!("expected Missing, got Was"),
+        }
+    }
+
+    #[test]
+    fn remove_refuses_to_delete_the_pinned_default_version() {
+        let dir = tempdir().unwrap();
+        let inventory_dir = dir.path().to_path_buf();
+        fs::create_dir_all(inventory_dir.join("1.2.3")).unwrap();
+
+        let mut collection: Collection<TestDistro> = Collection::load(inventory_dir.clone()).unwrap();
+        let result = collection.remove(&version("1.2.3"), Some(&version("1.2.3")));
+
+        assert!(result.is_err());
+        assert!(inventory_dir.join("1.2.3").is_dir());
+        assert!(collection.contains(&version("1.2.3")));
+    }
+
+    #[test]
+    fn remove_deletes_the_install_dir_and_updates_the_cache() {
+        let dir = tempdir().unwrap();
+        let inventory_dir = dir.path().to_path_buf();
+        fs::create_dir_all(inventory_dir.join("1.2.3")).unwrap();
+
+        let mut collection: Collection<TestDistro> = Collection::load(inventory_dir.clone()).unwrap();
+        match collection.remove(&version("1.2.3"), None).unwrap() {
+            Removed::Was(removed) => assert_eq!(removed, version("1.2.3")),
+            Removed::Missing(_) => panic!("expected Was, got Missing"),
+        }
+
+        assert!(!inventory_dir.join("1.2.3").is_dir());
+        assert!(!collection.contains(&version("1.2.3")));
+
+        // Re-loading from disk should agree: the persisted cache was updated, not just the
+        // in-memory copy.
+        let reloaded: Collection<TestDistro> = Collection::load(inventory_dir).unwrap();
+        assert!(!reloaded.contains(&version("1.2.3")));
+    }
+
+    #[test]
+    fn force_refetch_replaces_an_existing_install_and_still_reports_now() {
+        let dir = tempdir().unwrap();
+        let inventory_dir = dir.path().to_path_buf();
+        let mut collection: Collection<TestDistro> = Collection::load(inventory_dir.clone()).unwrap();
+
+        let fetched = collection
+            .fetch(&version("2.0.0"), false, &mut NoProgress, |_progress| {
+                Ok(TestDistro {
+                    version: version("2.0.0"),
+                    marker: "first".into(),
+                })
+            })
+            .unwrap();
+        assert!(matches!(fetched, Fetched::Now(_)));
+        assert_eq!(
+            fs::read_to_string(inventory_dir.join("2.0.0").join("marker")).unwrap(),
+            "first"
+        );
+
+        // Without force, a second fetch is a no-op: Already, and the existing install is left
+        // untouched.
+        let fetched = collection
+            .fetch(&version("2.0.0"), false, &mut NoProgress, |_progress| {
+                Ok(TestDistro {
+                    version: version("2.0.0"),
+                    marker: "second".into(),
+                })
+            })
+            .unwrap();
+        assert!(matches!(fetched, Fetched::Already(_)));
+        assert_eq!(
+            fs::read_to_string(inventory_dir.join("2.0.0").join("marker")).unwrap(),
+            "first"
+        );
+
+        // With force, the short-circuit is bypassed: the distro is reconstructed and its
+        // install directory is replaced, and the result still reports Now.
+        let fetched = collection
+            .fetch(&version("2.0.0"), true, &mut NoProgress, |_progress| {
+                Ok(TestDistro {
+                    version: version("2.0.0"),
+                    marker: "third".into(),
+                })
+            })
+            .unwrap();
+        assert!(matches!(fetched, Fetched::Now(_)));
+        assert_eq!(
+            fs::read_to_string(inventory_dir.join("2.0.0").join("marker")).unwrap(),
+            "third"
+        );
+    }
+
+    #[test]
+    fn fetch_never_constructs_the_distro_when_already_cached() {
+        let dir = tempdir().unwrap();
+        let inventory_dir = dir.path().to_path_buf();
+        fs::create_dir_all(inventory_dir.join("1.2.3")).unwrap();
+
+        let mut collection: Collection<TestDistro> = Collection::load(inventory_dir.clone()).unwrap();
+        assert!(collection.contains(&version("1.2.3")));
+
+        let construct_calls = Rc::new(Cell::new(0));
+        let calls = Rc::clone(&construct_calls);
+        let fetched = collection
+            .fetch(&version("1.2.3"), false, &mut NoProgress, |_progress| {
+                calls.set(calls.get() + 1);
+                panic!("construct should not be called when already cached and not forced");
+            })
+            .unwrap();
+
+        assert!(matches!(fetched, Fetched::Already(_)));
+        assert_eq!(construct_calls.get(), 0);
+    }
+
+    #[test]
+    fn fetch_persists_the_cache_after_a_successful_fetch() {
+        let dir = tempdir().unwrap();
+        let inventory_dir = dir.path().to_path_buf();
+        let mut collection: Collection<TestDistro> = Collection::load(inventory_dir.clone()).unwrap();
+
+        let construct_calls = Rc::new(Cell::new(0));
+        let calls = Rc::clone(&construct_calls);
+        let fetched = collection
+            .fetch(&version("3.4.5"), false, &mut NoProgress, move |_progress| {
+                calls.set(calls.get() + 1);
+                Ok(TestDistro {
+                    version: version("3.4.5"),
+                    marker: "fetched".into(),
+                })
+            })
+            .unwrap();
+
+        assert!(matches!(fetched, Fetched::Now(_)));
+        assert_eq!(construct_calls.get(), 1);
+
+        // The persisted cache, not just the in-memory copy, should reflect the new version.
+        let reloaded: Collection<TestDistro> = Collection::load(inventory_dir).unwrap();
+        assert!(reloaded.contains(&version("3.4.5")));
+    }
+}